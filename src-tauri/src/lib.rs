@@ -1,8 +1,282 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
 use tauri::Manager;
-use tauri::menu::{Menu, MenuItem};
-use tauri::tray::{TrayIconBuilder, TrayIconEvent, MouseButton};
+use tauri::menu::{AboutMetadataBuilder, CheckMenuItem, Menu, MenuItem, PredefinedMenuItem};
+use tauri::tray::{TrayIcon, TrayIconBuilder, TrayIconEvent, MouseButton};
+use tauri_plugin_autostart::ManagerExt as _;
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+use tauri_plugin_notification::NotificationExt;
+use tauri_plugin_store::StoreExt;
+use tauri_plugin_updater::UpdaterExt;
 use window_vibrancy::{apply_vibrancy, NSVisualEffectMaterial};
 
+const LAUNCH_AT_LOGIN_KEY: &str = "launch_at_login";
+const ALWAYS_ON_ALL_WORKSPACES_KEY: &str = "always_on_all_workspaces";
+const DOCK_ICON_VISIBLE_KEY: &str = "dock_icon_visible";
+
+/// Switches between menu-bar-only ("accessory", no Dock icon) and regular
+/// ("Dock + app-switcher visible") on macOS, and persists the choice so it
+/// survives restarts. Other platforms have no equivalent policy, so there
+/// only the stored preference is written.
+#[tauri::command]
+fn set_dock_icon_visible(app: tauri::AppHandle, visible: bool) -> Result<(), String> {
+  #[cfg(target_os = "macos")]
+  {
+    // Stay `Regular` while the main window is showing (see
+    // `show_main_window`); the `CloseRequested` handler drops back to
+    // `Accessory` once it's hidden.
+    let main_window_visible = app
+      .get_webview_window("main")
+      .and_then(|window| window.is_visible().ok())
+      .unwrap_or(false);
+    app.set_activation_policy(if visible || main_window_visible {
+      tauri::ActivationPolicy::Regular
+    } else {
+      tauri::ActivationPolicy::Accessory
+    });
+  }
+  #[cfg(not(target_os = "macos"))]
+  let _ = &app;
+
+  let store = app.store("settings.json").map_err(|e| e.to_string())?;
+  store.set(DOCK_ICON_VISIBLE_KEY, visible);
+  Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn dock_icon_visible(app: &tauri::AppHandle) -> bool {
+  app
+    .store("settings.json")
+    .ok()
+    .and_then(|store| store.get(DOCK_ICON_VISIBLE_KEY).and_then(|value| value.as_bool()))
+    .unwrap_or(false)
+}
+
+/// Shows, unminimizes, and focuses the main window. A macOS app running
+/// under `ActivationPolicy::Accessory` can't become the key/frontmost app,
+/// so the window would otherwise appear unfocused and behind other apps;
+/// switch to `Regular` for as long as the window stays visible, and let the
+/// `CloseRequested` handler switch back once it's hidden again.
+fn show_main_window(app: &tauri::AppHandle) {
+  let Some(window) = app.get_webview_window("main") else {
+    return;
+  };
+
+  #[cfg(target_os = "macos")]
+  if !dock_icon_visible(app) {
+    app.set_activation_policy(tauri::ActivationPolicy::Regular);
+  }
+
+  let _ = window.show();
+  let _ = window.unminimize();
+  let _ = window.set_focus();
+}
+
+/// Companion activity surfaced on the tray icon without needing to open a
+/// window.
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrayStatus {
+  Idle,
+  Busy,
+  NotificationPending,
+}
+
+impl TrayStatus {
+  fn icon_bytes(self) -> &'static [u8] {
+    match self {
+      TrayStatus::Idle => include_bytes!("../icons/tray-icon.png"),
+      TrayStatus::Busy => include_bytes!("../icons/tray-icon-busy.png"),
+      TrayStatus::NotificationPending => include_bytes!("../icons/tray-icon-notification.png"),
+    }
+  }
+
+  fn tooltip(self) -> &'static str {
+    match self {
+      TrayStatus::Idle => "Companion",
+      TrayStatus::Busy => "Companion — working…",
+      TrayStatus::NotificationPending => "Companion — new notification",
+    }
+  }
+}
+
+/// Decodes an embedded PNG into a Tauri tray icon image, falling back to
+/// `None` so callers can keep the app's default icon on failure.
+fn load_tray_icon(bytes: &[u8]) -> Option<tauri::image::Image<'static>> {
+  let icon_image = image::load_from_memory(bytes).ok()?;
+  let width = icon_image.width();
+  let height = icon_image.height();
+  let rgba = icon_image.into_rgba8().into_vec();
+  Some(tauri::image::Image::new_owned(rgba, width, height))
+}
+
+/// Swaps the tray icon and tooltip to reflect the companion's current
+/// status, so the frontend can surface activity without opening a window.
+#[tauri::command]
+fn set_tray_status(app: tauri::AppHandle, status: TrayStatus) -> Result<(), String> {
+  let tray = app.state::<TrayIcon>();
+  if let Some(icon) = load_tray_icon(status.icon_bytes()) {
+    tray.set_icon(Some(icon)).map_err(|e| e.to_string())?;
+  }
+  tray
+    .set_tooltip(Some(status.tooltip()))
+    .map_err(|e| e.to_string())?;
+  Ok(())
+}
+
+/// Checks for an update and, when one is found, either asks whether to
+/// install it and restart, installing only on confirmation (the interactive
+/// "Check for Updates…" path), or flags it on the tray icon and posts a
+/// notification (the silent startup check), so the companion keeps itself
+/// current without nagging.
+async fn check_for_updates(app: tauri::AppHandle, interactive: bool) {
+  let Ok(updater) = app.updater() else {
+    return;
+  };
+  let Ok(Some(update)) = updater.check().await else {
+    return;
+  };
+
+  if !interactive {
+    let _ = set_tray_status(app.clone(), TrayStatus::NotificationPending);
+    let _ = app
+      .notification()
+      .builder()
+      .title("Update available")
+      .body(format!("Version {} is ready to install", update.version))
+      .show();
+    return;
+  }
+
+  let confirmed = app
+    .dialog()
+    .message(format!(
+      "Version {} is available. Install it now and restart?",
+      update.version
+    ))
+    .title("Update Available")
+    .buttons(MessageDialogButtons::OkCancel)
+    .blocking_show();
+
+  if !confirmed {
+    return;
+  }
+
+  if update.download_and_install(|_, _| {}, || {}).await.is_ok() {
+    app.restart();
+  }
+}
+
+const TOGGLE_PANEL_SHORTCUT_KEY: &str = "toggle_panel_shortcut";
+const DEFAULT_TOGGLE_PANEL_SHORTCUT: &str = "CmdOrCtrl+Shift+Space";
+
+/// Tracks whether the app is genuinely exiting (via the tray "Quit" item) so
+/// the main window's `CloseRequested` handler knows when to let the close
+/// through instead of hiding the window.
+struct IsQuitting(AtomicBool);
+
+/// Caches the tray icon's last known bounding rect (refreshed when the
+/// pointer enters or moves over the icon, and on a left click) so the global
+/// shortcut can position the panel the same way a tray
+/// click would, even though it has no rect of its own to work from.
+struct LastTrayRect(Mutex<Option<tauri::Rect>>);
+
+/// Shows or hides the panel window, shared by the tray's left-click handler
+/// and the global shortcut so both toggle paths position it identically.
+fn toggle_panel(app: &tauri::AppHandle) {
+  let Some(window) = app.get_webview_window("panel") else {
+    return;
+  };
+
+  if window.is_visible().unwrap_or(false) {
+    let _ = window.hide();
+    return;
+  }
+
+  if let Some(rect) = app.state::<LastTrayRect>().0.lock().unwrap().clone() {
+    if let Ok(position) = compute_panel_position(&window, &rect) {
+      let _ = window.set_position(tauri::Position::Physical(position));
+    }
+  }
+
+  let _ = window.show();
+  let _ = window.set_focus();
+}
+
+/// Centers the panel in the current monitor's work area. Used as a fallback
+/// when there's no usable tray icon geometry to anchor to.
+fn center_in_work_area(
+  window: &tauri::WebviewWindow,
+  panel_width: i32,
+  panel_height: i32,
+) -> tauri::Result<tauri::PhysicalPosition<i32>> {
+  if let Some(monitor) = window.current_monitor()? {
+    let work_area = monitor.work_area();
+    return Ok(tauri::PhysicalPosition {
+      x: work_area.position.x + (work_area.size.width as i32 - panel_width) / 2,
+      y: work_area.position.y + (work_area.size.height as i32 - panel_height) / 2,
+    });
+  }
+
+  Ok(tauri::PhysicalPosition { x: 0, y: 0 })
+}
+
+/// Works out where the panel popover should land relative to the tray icon's
+/// bounding rect, clamped so it never spills off the current monitor.
+///
+/// - On macOS the menu bar lives at the top of the screen, so the panel drops
+///   down below the tray icon.
+/// - On Windows/Linux the taskbar (and therefore the tray icon) is usually at
+///   the bottom, so the panel's bottom edge is anchored just above the icon.
+fn compute_panel_position(
+  window: &tauri::WebviewWindow,
+  tray_rect: &tauri::Rect,
+) -> tauri::Result<tauri::PhysicalPosition<i32>> {
+  let scale_factor = window.scale_factor()?;
+  let tray_position = tray_rect.position.to_physical::<i32>(scale_factor);
+  let tray_size = tray_rect.size.to_physical::<i32>(scale_factor);
+  let panel_size = window.outer_size()?;
+  let panel_width = panel_size.width as i32;
+  let panel_height = panel_size.height as i32;
+
+  // Some tray backends (several Linux ones in particular) report an empty
+  // or zeroed rect instead of real geometry. Anchoring to that would pin the
+  // panel in a monitor corner instead of near the icon, so fall back to
+  // centering it in the work area.
+  if tray_size.width <= 0 || tray_size.height <= 0 {
+    return center_in_work_area(window, panel_width, panel_height);
+  }
+
+  let tray_center_x = tray_position.x + tray_size.width / 2;
+
+  #[cfg(target_os = "macos")]
+  let mut position = tauri::PhysicalPosition {
+    x: tray_center_x - panel_width / 2,
+    y: tray_position.y + tray_size.height,
+  };
+
+  #[cfg(not(target_os = "macos"))]
+  let mut position = tauri::PhysicalPosition {
+    x: tray_center_x - panel_width / 2,
+    y: tray_position.y - panel_height,
+  };
+
+  if let Some(monitor) = window.current_monitor()? {
+    let work_area = monitor.work_area();
+    let min_x = work_area.position.x;
+    let min_y = work_area.position.y;
+    let max_x = work_area.position.x + work_area.size.width as i32 - panel_width;
+    let max_y = work_area.position.y + work_area.size.height as i32 - panel_height;
+
+    position.x = position.x.clamp(min_x, max_x.max(min_x));
+    position.y = position.y.clamp(min_y, max_y.max(min_y));
+  }
+
+  Ok(position)
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
   tauri::Builder::default()
@@ -15,91 +289,237 @@ pub fn run() {
       {
         apply_vibrancy(&main_window, NSVisualEffectMaterial::HudWindow, None, None)
           .expect("Unsupported platform! 'apply_vibrancy' is only supported on macOS");
-        
+
         apply_vibrancy(&panel_window, NSVisualEffectMaterial::HudWindow, None, None)
           .expect("Unsupported platform! 'apply_vibrancy' is only supported on macOS");
+      }
 
-        // Prevent main window from closing on macOS
-        let main_window_clone = main_window.clone();
-        main_window.on_window_event(move |event| {
-          if let tauri::WindowEvent::CloseRequested { api, .. } = event {
-            api.prevent_close();
-            let _ = main_window_clone.hide();
+      app.manage(IsQuitting(AtomicBool::new(false)));
+      app.manage(LastTrayRect(Mutex::new(None)));
+
+      app.handle().plugin(tauri_plugin_store::Builder::default().build())?;
+
+      let store = app.store("settings.json")?;
+      let toggle_panel_shortcut = store
+        .get(TOGGLE_PANEL_SHORTCUT_KEY)
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_else(|| DEFAULT_TOGGLE_PANEL_SHORTCUT.to_string());
+      store.set(TOGGLE_PANEL_SHORTCUT_KEY, toggle_panel_shortcut.clone());
+
+      // A malformed stored accelerator, or one that collides with an OS/other
+      // app hotkey, must never prevent the companion from starting: fall
+      // back to the default on a bad parse, and just log a registration
+      // failure instead of propagating it out of `setup()`.
+      let shortcut: Shortcut = toggle_panel_shortcut
+        .parse()
+        .unwrap_or_else(|_| DEFAULT_TOGGLE_PANEL_SHORTCUT.parse().expect("default accelerator is valid"));
+      app.handle().plugin(
+        tauri_plugin_global_shortcut::Builder::new()
+          .with_handler(|app, _shortcut, event| {
+            if event.state == ShortcutState::Pressed {
+              toggle_panel(app);
+            }
+          })
+          .build(),
+      )?;
+      if let Err(e) = app.global_shortcut().register(shortcut) {
+        eprintln!("failed to register the toggle-panel shortcut: {e}");
+      }
+
+      // Closing the main window just hides it, so the tray companion keeps
+      // running; the tray's "Quit" item flips `IsQuitting` first so this
+      // still lets the app exit cleanly on every platform.
+      let main_window_clone = main_window.clone();
+      let app_handle = app.handle().clone();
+      main_window.on_window_event(move |event| {
+        if let tauri::WindowEvent::CloseRequested { api, .. } = event {
+          if app_handle.state::<IsQuitting>().0.load(Ordering::SeqCst) {
+            return;
           }
+          api.prevent_close();
+          let _ = main_window_clone.hide();
+
+          #[cfg(target_os = "macos")]
+          if !dock_icon_visible(&app_handle) {
+            app_handle.set_activation_policy(tauri::ActivationPolicy::Accessory);
+          }
+        }
+      });
+
+      app.handle().plugin(tauri_plugin_autostart::init(
+        tauri_plugin_autostart::MacosLauncher::LaunchAgent,
+        None,
+      ))?;
+
+      let launch_at_login = store
+        .get(LAUNCH_AT_LOGIN_KEY)
+        .and_then(|value| value.as_bool())
+        .unwrap_or(false);
+      if launch_at_login {
+        let _ = app.autolaunch().enable();
+      } else {
+        let _ = app.autolaunch().disable();
+      }
+
+      let always_on_all_workspaces = store
+        .get(ALWAYS_ON_ALL_WORKSPACES_KEY)
+        .and_then(|value| value.as_bool())
+        .unwrap_or(true);
+
+      // Keep the popover reachable regardless of which Space/virtual desktop
+      // is active, unless the user disabled it from the tray menu.
+      let _ = panel_window.set_visible_on_all_workspaces(always_on_all_workspaces);
+      let _ = panel_window.set_always_on_top(always_on_all_workspaces);
+
+      // Tray-first companions don't want a Dock icon by default; accessory
+      // mode keeps the app in the menu bar only, unless the user opted back
+      // into Regular mode.
+      #[cfg(target_os = "macos")]
+      {
+        let dock_icon_visible = store
+          .get(DOCK_ICON_VISIBLE_KEY)
+          .and_then(|value| value.as_bool())
+          .unwrap_or(false);
+        app.set_activation_policy(if dock_icon_visible {
+          tauri::ActivationPolicy::Regular
+        } else {
+          tauri::ActivationPolicy::Accessory
         });
       }
 
-      let tray_icon = if let Ok(icon_image) = image::load_from_memory(include_bytes!("../icons/tray-icon.png")) {
-        let width = icon_image.width();
-        let height = icon_image.height();
-        let rgba = icon_image.into_rgba8().into_vec();
-        Some(tauri::image::Image::new_owned(rgba, width, height))
-      } else {
-        None
-      };
+      let tray_icon = load_tray_icon(TrayStatus::Idle.icon_bytes());
 
       // Create Tray Menu
-      let quit_i = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
-      let show_i = MenuItem::with_id(app, "show", "Show Main Window", true, None::<&str>)?;
-      let menu = Menu::with_items(app, &[&show_i, &quit_i])?;
+      let show_i = MenuItem::with_id(app, "show", "Show Main Window", true, Some("CmdOrCtrl+O"))?;
+      let launch_at_login_i = CheckMenuItem::with_id(
+        app,
+        "launch_at_login",
+        "Launch at Login",
+        true,
+        launch_at_login,
+        None::<&str>,
+      )?;
+      let always_on_all_workspaces_i = CheckMenuItem::with_id(
+        app,
+        "always_on_all_workspaces",
+        "Always Show Panel on All Workspaces",
+        true,
+        always_on_all_workspaces,
+        None::<&str>,
+      )?;
+      let about_i = PredefinedMenuItem::about(
+        app,
+        Some("About Companion"),
+        Some(
+          AboutMetadataBuilder::new()
+            .version(Some(app.package_info().version.to_string()))
+            .authors(Some(vec![app.package_info().authors.to_string()]))
+            .build(),
+        ),
+      )?;
+      let check_updates_i = MenuItem::with_id(app, "check_updates", "Check for Updates…", true, None::<&str>)?;
+      let separator_i = PredefinedMenuItem::separator(app)?;
+      let separator2_i = PredefinedMenuItem::separator(app)?;
+      let quit_i = MenuItem::with_id(app, "quit", "Quit", true, Some("CmdOrCtrl+Q"))?;
+      let menu = Menu::with_items(
+        app,
+        &[
+          &show_i,
+          &launch_at_login_i,
+          &always_on_all_workspaces_i,
+          &separator_i,
+          &check_updates_i,
+          &about_i,
+          &separator2_i,
+          &quit_i,
+        ],
+      )?;
 
-      let _tray = TrayIconBuilder::new()
+      let panel_window_clone = panel_window.clone();
+
+      let tray = TrayIconBuilder::new()
         .icon(tray_icon.unwrap_or_else(|| app.default_window_icon().unwrap().clone()))
         .icon_as_template(true)
         .menu(&menu)
-        .on_menu_event(|app, event| {
+        .on_menu_event(move |app, event| {
           match event.id.as_ref() {
             "quit" => {
+              app.state::<IsQuitting>().0.store(true, Ordering::SeqCst);
               app.exit(0);
             }
             "show" => {
-              if let Some(window) = app.get_webview_window("main") {
-                let _ = window.show();
-                let _ = window.unminimize();
-                let _ = window.set_focus();
+              show_main_window(app);
+            }
+            "launch_at_login" => {
+              let enabled = launch_at_login_i.is_checked().unwrap_or(false);
+              let store = app.store("settings.json").expect("settings store is registered");
+              store.set(LAUNCH_AT_LOGIN_KEY, enabled);
+              let result = if enabled {
+                app.autolaunch().enable()
+              } else {
+                app.autolaunch().disable()
+              };
+              if result.is_err() {
+                let _ = launch_at_login_i.set_checked(!enabled);
               }
             }
+            "always_on_all_workspaces" => {
+              let enabled = always_on_all_workspaces_i.is_checked().unwrap_or(false);
+              let store = app.store("settings.json").expect("settings store is registered");
+              store.set(ALWAYS_ON_ALL_WORKSPACES_KEY, enabled);
+              let _ = panel_window_clone.set_visible_on_all_workspaces(enabled);
+              let _ = panel_window_clone.set_always_on_top(enabled);
+            }
+            "check_updates" => {
+              let app = app.clone();
+              tauri::async_runtime::spawn(async move {
+                check_for_updates(app, true).await;
+              });
+            }
             _ => {}
           }
         })
         .on_tray_icon_event(|tray, event| {
-            if let TrayIconEvent::Click { button: MouseButton::Left, position, .. } = event {
-                let app = tray.app_handle();
-                if let Some(window) = app.get_webview_window("panel") {
-                    if window.is_visible().unwrap_or(false) {
-                        let _ = window.hide();
-                    } else {
-                        // Basic positioning - on macOS tray is at the top
-                        let _ = window.set_position(tauri::Position::Physical(tauri::PhysicalPosition { 
-                            x: (position.x - 190.0) as i32,
-                            y: 0 
-                        }));
-                        let _ = window.show();
-                        let _ = window.set_focus();
-                    }
+            let app = tray.app_handle();
+            match event {
+                TrayIconEvent::Enter { rect, .. } | TrayIconEvent::Move { rect, .. } => {
+                    *app.state::<LastTrayRect>().0.lock().unwrap() = Some(rect);
+                }
+                TrayIconEvent::Click { button: MouseButton::Left, rect, .. } => {
+                    *app.state::<LastTrayRect>().0.lock().unwrap() = Some(rect);
+                    toggle_panel(app);
                 }
+                _ => {}
             }
         })
         .build(app)?;
 
-      app.handle().plugin(tauri_plugin_store::Builder::default().build())?;
+      app.manage(tray);
+
       app.handle().plugin(tauri_plugin_fs::init())?;
       app.handle().plugin(tauri_plugin_dialog::init())?;
       app.handle().plugin(tauri_plugin_http::init())?;
       app.handle().plugin(tauri_plugin_shell::init())?;
       app.handle().plugin(tauri_plugin_notification::init())?;
+      app.handle().plugin(tauri_plugin_updater::Builder::new().build())?;
+
+      // Silent background check: only flags the tray icon and posts a
+      // notification if an update is actually pending, so the companion
+      // doesn't nag on every launch.
+      let app_handle = app.handle().clone();
+      tauri::async_runtime::spawn(async move {
+        check_for_updates(app_handle, false).await;
+      });
+
       Ok(())
     })
+    .invoke_handler(tauri::generate_handler![set_tray_status, set_dock_icon_visible])
     .build(tauri::generate_context!())
     .expect("error while building tauri application")
     .run(|app_handle, event| match event {
       #[cfg(target_os = "macos")]
       tauri::RunEvent::Reopen { .. } => {
-        if let Some(window) = app_handle.get_webview_window("main") {
-          let _ = window.show();
-          let _ = window.unminimize();
-          let _ = window.set_focus();
-        }
+        show_main_window(app_handle);
       }
       _ => {}
     });